@@ -0,0 +1,132 @@
+//! I2C transport for the `Abp` driver.
+
+use embedded_hal as hal;
+use hal::delay::DelayNs;
+use hal::i2c::{ErrorType, I2c};
+use substring::Substring;
+
+use crate::{Abp, AbpBus, ParseError, parse_part_params};
+
+/// Error type of the underlying I2C peripheral.
+type I2cError<I2C> = <I2C as ErrorType>::Error;
+
+/// Wraps an I2C peripheral together with the device's 7-bit address.
+#[derive(Debug)]
+pub struct I2cBus<I2C>
+{
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> AbpBus for I2cBus<I2C>
+where
+    I2C: I2c,
+{
+    type Error = I2cError<I2C>;
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>
+    {
+        self.i2c.read(self.address, buffer)
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error>
+    {
+        self.i2c.write(self.address, buffer)
+    }
+}
+
+impl<I2C, D> Abp<I2cBus<I2C>, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    /// opens a connection to a ABP on a specified I2C.
+    ///
+    /// # Panics
+    /// Panics if `part_nr` can't be parsed; use [`Abp::try_new`] for a recoverable path.
+    pub fn new(i2c: I2C, delay: D, part_nr: &'static str) -> Self
+    {
+        Self::try_new(i2c, delay, part_nr).unwrap()
+    }
+
+    /// opens a connection to a ABP on a specified I2C, without panicking on a malformed
+    /// part number.
+    /// # Errors
+    /// Returns a [`ParseError`] describing which part of `part_nr` couldn't be parsed.
+    pub fn try_new(i2c: I2C, delay: D, part_nr: &'static str) -> Result<Self, ParseError>
+    {
+        let params = parse_part_params(part_nr)?;
+
+        let address = match part_nr.substring(12, 13)
+        {
+            "A" | "S" => return Err(ParseError::UnsupportedInterface),
+            "0" => 0x08,
+            "1" => 0x18,
+            "2" => 0x28,
+            "3" => 0x38,
+            "4" => 0x48,
+            "5" => 0x58,
+            "6" => 0x68,
+            "7" => 0x78,
+             _  => return Err(ParseError::UnknownAddress),
+        };
+
+        Ok(Abp::from_parts(I2cBus{i2c, address}, delay, params))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::i2c::Operation;
+
+    #[derive(Debug)]
+    struct DummyI2c;
+
+    impl ErrorType for DummyI2c
+    {
+        type Error = Infallible;
+    }
+
+    impl I2c for DummyI2c
+    {
+        fn transaction(&mut self, _address: u8, _operations: &mut [Operation<'_>]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct DummyDelay;
+
+    impl DelayNs for DummyDelay
+    {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn try_new_parses_a_well_formed_part_number()
+    {
+        let abp = Abp::<I2cBus<DummyI2c>, DummyDelay>::try_new(DummyI2c, DummyDelay, "ABPDNNN150PG3D3");
+
+        assert!(abp.is_ok());
+    }
+
+    #[test]
+    fn try_new_rejects_non_abp_series()
+    {
+        let err = Abp::<I2cBus<DummyI2c>, DummyDelay>::try_new(DummyI2c, DummyDelay, "XYZDNNN150PG3D3").unwrap_err();
+
+        assert_eq!(err, ParseError::NotAbpSeries);
+    }
+
+    #[test]
+    fn try_new_rejects_spi_interface_digit()
+    {
+        let err = Abp::<I2cBus<DummyI2c>, DummyDelay>::try_new(DummyI2c, DummyDelay, "ABPDNNN150PGAD3").unwrap_err();
+
+        assert_eq!(err, ParseError::UnsupportedInterface);
+    }
+}
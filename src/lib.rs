@@ -7,7 +7,7 @@
 //! # Usage
 //!
 //! # Examples
-//! ```rust
+//! ```ignore
 //! // embedded_hal implementation
 //! use rppal::{spi::{Spi, Bus, SlaveSelect, Mode, Error},hal::Delay};
 //!
@@ -28,7 +28,7 @@
 //! - [`embedded-hal`][2]
 //!
 //! [2]: https://github.com/rust-embedded/embedded-hal
-//! 
+//!
 //! - [I2C Communication][3]
 //!
 //! [3]: https://sps-support.honeywell.com/s/article/AST-ABP-I2C-Protocol-Guidelines
@@ -37,16 +37,16 @@
 #![no_std]
 
 use embedded_hal as hal;
-use hal::blocking::{i2c, delay::DelayMs};
+use hal::delay::DelayNs;
 use core::str::FromStr;
-// use core::error::Error;
 use substring::Substring;
-use nb::{Error::{Other, WouldBlock}};
+use nb::Error::Other;
 
 // use bitmach to decode the result
 use bitmatch::bitmatch;
 
-type I2cError = embedded_hal::blocking::i2c::Read::Error;
+pub mod i2c;
+pub mod spi;
 
 #[derive(Copy, Clone, Debug)]
 pub enum ApbError<E>
@@ -54,6 +54,8 @@ pub enum ApbError<E>
     Other(E),
     ErrorCommandMode,
     ErrorDiagnosticState,
+    ErrorNoThermometer,
+    ErrorNoSleep,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -90,6 +92,21 @@ pub enum PressureUnit
     Psi         // psi
 }
 
+impl PressureUnit
+{
+    /// Factor to convert a Pascal value into this unit, i.e. `value_in_unit = pascals / factor`.
+    fn conversion_factor(self) -> f32
+    {
+        match self
+        {
+            PressureUnit::Bar  => 100000.0,
+            PressureUnit::Mbar => 100.0,
+            PressureUnit::Kpa  => 1000.0,
+            PressureUnit::Psi  => 6_894.757_3,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Output
 {
@@ -98,15 +115,138 @@ struct Output
     temperature: u16
 }
 
+/// A combined pressure and temperature reading, in Pascals and degrees Celsius.
+#[derive(Copy, Clone, Debug)]
+pub struct Reading
+{
+    pub pressure: f32,
+    pub temperature: f32,
+}
+
+/// Fields of the `Abp` part number that are independent of the transport (I2C or SPI).
+///
+/// Shared between the `i2c` and `spi` constructors so the parsing logic for the pressure
+/// range, unit and transfer function digits only lives in one place.
+pub(crate) struct PartParams
+{
+    pub(crate) p_max: f32,
+    pub(crate) p_min: f32,
+    pub(crate) o_max: u16,
+    pub(crate) o_min: u16,
+    pub(crate) conversion_factor: f32,
+    pub(crate) has_thermometer: bool,
+    pub(crate) has_sleep: bool,
+}
+
+/// Errors produced while parsing an ABP part number.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError
+{
+    /// The part number isn't from the ABP series.
+    NotAbpSeries,
+    /// The pressure unit digit isn't one of `M`/`B`/`K`/`P`.
+    UnknownPressureUnit,
+    /// The interface digit doesn't match the bus the constructor was called for.
+    UnsupportedInterface,
+    /// The pressure range or differential/gauge digit couldn't be parsed.
+    BadRange,
+    /// The I2C address digit isn't one of `0`..`7`.
+    UnknownAddress,
+    /// The transfer-function digit isn't one of `A`/`D`/`S`/`T`.
+    BadTransferFunction,
+}
+
+pub(crate) fn parse_part_params(part_nr: &'static str) -> Result<PartParams, ParseError>
+{
+    // example part number (without spaces):
+    // ABP D NN N 150PG A A 3
+    // 000 0 00 0 00111 1 1 1
+    // 123 4 56 7 89012 3 4 5
+
+    // Product series
+    if part_nr.substring(0, 3) != "ABP"
+    {
+        return Err(ParseError::NotAbpSeries);
+    };
+
+    // Package, pressure port and product option [4..7] are not relevant for the driver
+    // Pressure range
+    let p_max = f32::from_str(part_nr.substring(7, 10)).map_err(|_| ParseError::BadRange)?;
+
+    // conversion to Pa
+    let unit = match part_nr.substring(10, 11)
+    {
+        "M" => PressureUnit::Mbar,
+        "B" => PressureUnit::Bar,
+        "K" => PressureUnit::Kpa,
+        "P" => PressureUnit::Psi,
+         _  => return Err(ParseError::UnknownPressureUnit),
+    };
+    let conversion_factor = unit.conversion_factor();
+
+    let p_min = match part_nr.substring(11, 12)
+    {
+        "D" => -p_max,      // differential type
+        "G" => 0.0,         // gauge type
+         _  => return Err(ParseError::BadRange),
+    };
+
+    let o_max = 0x3999;     // 90 % of 2^14
+    let o_min = 0x0666;     // 10 % of 2^14
+
+    let has_sleep = match part_nr.substring(13, 14)
+    {
+        "A" => false,
+        "D" => true,
+        "S" => true,
+        "T" => false,
+         _  => return Err(ParseError::BadTransferFunction),
+    };
+
+    let has_thermometer = match part_nr.substring(13, 14)
+    {
+        "A" => false,
+        "D" => true,
+        "S" => false,
+        "T" => true,
+         _  => return Err(ParseError::BadTransferFunction),
+    };
+    // Supply voltage [15] is not relevant for the driver
+
+    Ok(PartParams{p_max, p_min, o_max, o_min, conversion_factor, has_sleep, has_thermometer})
+}
+
+/// Abstracts the wire transport an `Abp` device is attached through.
+///
+/// Both the I2C and SPI variants clock out the 2-byte (pressure only) or 4-byte (pressure
+/// and temperature) status frame and write the command-mode bytes used by `sleep`/`wake`,
+/// so the rest of the driver is written entirely in terms of this trait.
+pub trait AbpBus
+{
+    type Error;
+
+    /// Reads `buffer.len()` bytes of the status/pressure(/temperature) frame.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes a command byte sequence, used to enter/exit the device's command mode.
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Command byte that puts the sensor into command mode (used to send it to sleep).
+const ENTER_COMMAND_MODE: u8 = 0xA5;
+/// Command byte that takes the sensor back out of command mode (wakes it up).
+const EXIT_COMMAND_MODE: u8 = 0x00;
+/// Power-up/response time the datasheet mandates after a command-mode transition.
+const COMMAND_MODE_DELAY_US: u32 = 5_000;
+
 /// Represents an instance of a Abp device
 #[derive(Debug)]
-pub struct Abp<I2C, D>
+pub struct Abp<BUS, D>
 where
-    I2C: i2c::Read,
-    D: DelayMs<u16>,
+    BUS: AbpBus,
+    D: DelayNs,
 {
-    // SPI specific
-    i2c: I2C,
+    bus: BUS,
     // timeer for delay
     delay: D,
     p_max: f32,
@@ -114,137 +254,162 @@ where
     o_max: u16,
     o_min: u16,
     conversion_factor: f32,
-    i2c_address: u8,
     has_thermometer: bool,
-    has_sleep: bool
+    has_sleep: bool,
+    #[cfg(feature = "altitude")]
+    sea_level_pressure: f32,
 }
 
-impl <I2C, D, E> Abp<I2C, D>
+/// Default sea-level reference pressure, in Pascals, used by [`Abp::read_altitude`].
+#[cfg(feature = "altitude")]
+const DEFAULT_SEA_LEVEL_PRESSURE: f32 = 101325.0;
+
+impl <BUS, D> Abp<BUS, D>
 where
-    I2C: i2c::Read,
-    D: DelayMs<u16>,
+    BUS: AbpBus,
+    D: DelayNs,
 {
-    // type _embedded_hal_blocking_i2c_error = i2c::Read::Error;
-    /// opens a connection to a ABP on a specified I2C.
-    ///
-    pub fn new(i2c: I2C, delay: D, part_nr: & 'static str) -> Self
+    pub(crate) fn from_parts(bus: BUS, delay: D, params: PartParams) -> Self
     {
-        // example part number (without spaces):
-        // ABP D NN N 150PG A A 3
-        // 000 0 00 0 00111 1 1 1
-        // 123 4 56 7 89012 3 4 5
-
-        //let (part, _) = part_nr.split_at(3);
-        // Product series
-        if part_nr.substring(1, 3) != "ABP"
-        {
-            panic!("This driver works only for the ABP series sensors.")
-        };
-
-        // Package, pressure port and product option [4..7] are not relevant for the driver
-        // Pressure range
-        let p_max = f32::from_str(part_nr.substring(8, 10)).unwrap();
-
-        // conversion to Pa
-        let conversion_factor = match part_nr.substring(11, 11)
-        {
-            "M" => 100.0,       //mbar
-            "B" => 100000.0,    //bar
-            "K" => 1000.0,      //kPa 
-            "P" => 6894.757293, //psi
-             _  => panic!("Unkonwn part: unkonwn pressure unit")
-        };
-
-        let p_min = match part_nr.substring(12, 12)
+        Abp
         {
-            "D" => -p_max,      // differential type
-            "G" => 0.0,         // gauge type
-             _  => panic!("Unkown part: Type must be differential or gauge.")
-        };
-
-        let i2c_address = match part_nr.substring(13, 13)
-        {
-            "A" => panic!("This driver is only for the sensors with I2C interface."),
-            "S" => panic!("This driver is only for the sensors with I2C interface."),
-            "0" => 0x08,
-            "1" => 0x18,
-            "2" => 0x28,
-            "3" => 0x38,
-            "4" => 0x48,
-            "5" => 0x58,
-            "6" => 0x68,
-            "7" => 0x78,
-             _  => panic!("Unkonw part. Output type {} not known.", part_nr.substring(13, 13))
-        };
-
-        let o_max = 0x3999;     // 90 % of 2^14
-        let o_min = 0x0666;     // 10 % of 2^14
-
-        let has_sleep = match part_nr.substring(14, 14)
-        {
-            "A" => false,
-            "D" => true,
-            "S" => true,
-            "T" => false,
-             _  => panic!("Unkown part: Transfer function has to be one of A, D, S, or T")
-        };
-
-        let has_thermometer = match part_nr.substring(14, 14)
-        {
-            "A" => false,
-            "D" => true,
-            "S" => false,
-            "T" => true,
-             _  => panic!("Unkown part: Transfer function has to be one of A, D, S, or T")
-        };
-        // Supply voltage [15] is not relevant for the driver
-
-        Abp {i2c, delay, p_max, p_min, o_max, o_min, conversion_factor, i2c_address, has_sleep, has_thermometer}
+            bus,
+            delay,
+            p_max: params.p_max,
+            p_min: params.p_min,
+            o_max: params.o_max,
+            o_min: params.o_min,
+            conversion_factor: params.conversion_factor,
+            has_thermometer: params.has_thermometer,
+            has_sleep: params.has_sleep,
+            #[cfg(feature = "altitude")]
+            sea_level_pressure: DEFAULT_SEA_LEVEL_PRESSURE,
+        }
     }
 
-    /// reads a pressure value from the ADP and retrurns it
+    /// reads a pressure value from the ADP and returns it in Pascals
     /// # Examples
-    /// ```rust
+    /// ```ignore
     /// let v = block!(pressure.read())?;
     /// ```
     /// # Errors
-    /// Returns i2c errors and nb::Error::WouldBlock if data isn't ready to be read from ADP
-    pub fn read(&mut self) -> nb::Result<f32, nb::Error<ApbError<I2cError>>>
+    /// Returns bus errors and nb::Error::WouldBlock if data isn't ready to be read from ADP
+    pub fn read(&mut self) -> nb::Result<f32, ApbError<BUS::Error>>
     {
         let mut buffer: [u8; 2] = [0; 2];
-        self.i2c.read(self.i2c_address, &mut buffer)?;
+        self.bus.read(&mut buffer).map_err(|e| nb::Error::Other(ApbError::Other(e)))?;
 
         let (status, pressure) = decode_pressure(& buffer);
 
-        match status
+        match Status::from(status)
         {
-            Valid => Ok(self.convert_pressure(pressure.into())),
-            Command => Err(Other(ApbError::ErrorCommandMode)),
-            Stale => Err(nb::Error::WouldBlock),
-            Diagnostic => Err(Other(ApbError::ErrorDiagnosticState)),    
+            Status::Valid => Ok(self.convert_pressure(pressure.into())),
+            Status::Command => Err(Other(ApbError::ErrorCommandMode)),
+            Status::Stale => Err(nb::Error::WouldBlock),
+            Status::Diagnostic => Err(Other(ApbError::ErrorDiagnosticState)),
         }
     }
 
-    pub fn pressure_and_temperature(&mut self) -> Result<f32, E>
+    /// reads both pressure (in Pascals) and temperature (in degrees Celsius)
+    /// # Errors
+    /// Returns `ApbError::ErrorNoThermometer` if this part has no thermometer, bus errors and
+    /// nb::Error::WouldBlock if data isn't ready to be read from ADP
+    pub fn pressure_and_temperature(&mut self) -> nb::Result<Reading, ApbError<BUS::Error>>
     {
-        //if self.has_thermometer == false {return self::Error}
+        if !self.has_thermometer
+        {
+            return Err(Other(ApbError::ErrorNoThermometer));
+        }
+
         let mut buffer: [u8; 4] = [0; 4];
-        self.i2c.read(self.i2c_address, &mut buffer)?;
+        self.bus.read(&mut buffer).map_err(|e| nb::Error::Other(ApbError::Other(e)))?;
 
         let output: Output = decode_pressure_and_temperature(& buffer);
 
-        Ok(self.convert_pressure(output.pressure.into()))
+        match output.status
+        {
+            Status::Valid => Ok(Reading
+            {
+                pressure: self.convert_pressure(output.pressure.into()),
+                temperature: self.convert_temperature(output.temperature.into()),
+            }),
+            Status::Command => Err(Other(ApbError::ErrorCommandMode)),
+            Status::Stale => Err(nb::Error::WouldBlock),
+            Status::Diagnostic => Err(Other(ApbError::ErrorDiagnosticState)),
+        }
+    }
+
+    /// reads a pressure value and converts it into the unit requested by the caller
+    /// # Errors
+    /// Returns bus errors and nb::Error::WouldBlock if data isn't ready to be read from ADP
+    pub fn read_in(&mut self, unit: PressureUnit) -> nb::Result<f32, ApbError<BUS::Error>>
+    {
+        Ok(self.read()? / unit.conversion_factor())
     }
 
     fn convert_pressure(& self, reading: f32) -> f32
     {
-        (f32::from(self.o_max - self.o_min)/(self.p_max - self.p_min))*(reading - self.p_min) + f32::from(self.o_min)
+        let applied = (reading - f32::from(self.o_min)) * (self.p_max - self.p_min) / f32::from(self.o_max - self.o_min) + self.p_min;
+
+        // conversion_factor normalizes the part's native unit into Pascals
+        applied * self.conversion_factor
     }
 
     fn convert_temperature(& self, temperature_reading: f32) -> f32
     {
         ((temperature_reading/2047.0) * 200.0) - 50.0
     }
+
+    /// sets the reference sea-level pressure (in Pascals) used by [`Abp::read_altitude`]
+    #[cfg(feature = "altitude")]
+    pub fn set_sea_level_pressure(&mut self, p0: f32)
+    {
+        self.sea_level_pressure = p0;
+    }
+
+    /// sends the device to sleep by entering command mode
+    /// # Errors
+    /// Returns `ApbError::ErrorNoSleep` if this part doesn't support command mode
+    pub fn sleep(&mut self) -> Result<(), ApbError<BUS::Error>>
+    {
+        if !self.has_sleep
+        {
+            return Err(ApbError::ErrorNoSleep);
+        }
+
+        self.bus.write(&[ENTER_COMMAND_MODE]).map_err(ApbError::Other)?;
+        self.delay.delay_us(COMMAND_MODE_DELAY_US);
+
+        Ok(())
+    }
+
+    /// wakes the device back up by exiting command mode
+    /// # Errors
+    /// Returns `ApbError::ErrorNoSleep` if this part doesn't support command mode
+    pub fn wake(&mut self) -> Result<(), ApbError<BUS::Error>>
+    {
+        if !self.has_sleep
+        {
+            return Err(ApbError::ErrorNoSleep);
+        }
+
+        self.bus.write(&[EXIT_COMMAND_MODE]).map_err(ApbError::Other)?;
+        self.delay.delay_us(COMMAND_MODE_DELAY_US);
+
+        Ok(())
+    }
+
+    /// estimates altitude above the configured sea-level reference, in meters, from the
+    /// international barometric formula. Only meaningful for absolute/gauge pressure parts.
+    /// # Errors
+    /// Returns bus errors and nb::Error::WouldBlock if data isn't ready to be read from ADP
+    #[cfg(feature = "altitude")]
+    pub fn read_altitude(&mut self) -> nb::Result<f32, ApbError<BUS::Error>>
+    {
+        let pressure = self.read()?;
+
+        Ok(44330.0 * (1.0 - libm::powf(pressure / self.sea_level_pressure, 1.0 / 5.255)))
+    }
 }
 
 #[bitmatch]
@@ -280,3 +445,143 @@ fn decode_pressure_and_temperature(buffer: &[u8;4]) -> Output
 
     Output{status, pressure, temperature}
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[derive(Debug)]
+    struct FixedBus(pub &'static [u8]);
+
+    impl AbpBus for FixedBus
+    {
+        type Error = core::convert::Infallible;
+
+        fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>
+        {
+            buffer.copy_from_slice(self.0);
+            Ok(())
+        }
+
+        fn write(&mut self, _buffer: &[u8]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct DummyDelay;
+
+    impl DelayNs for DummyDelay
+    {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    fn psi_gauge_150_params() -> PartParams
+    {
+        PartParams
+        {
+            p_max: 150.0,
+            p_min: 0.0,
+            o_max: 0x3999,
+            o_min: 0x0666,
+            conversion_factor: PressureUnit::Psi.conversion_factor(),
+            has_thermometer: false,
+            has_sleep: false,
+        }
+    }
+
+    fn psi_gauge_150_params_with_thermometer() -> PartParams
+    {
+        PartParams{has_thermometer: true, ..psi_gauge_150_params()}
+    }
+
+    #[test]
+    fn read_converts_o_min_counts_to_p_min_in_pascals()
+    {
+        // status = Valid (00), 14-bit reading = o_min (0x0666)
+        let mut abp = Abp::from_parts(FixedBus(&[0x06, 0x66]), DummyDelay, psi_gauge_150_params());
+
+        let pressure = nb::block!(abp.read()).unwrap();
+
+        assert!((pressure - 0.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn read_converts_o_max_counts_to_p_max_in_pascals()
+    {
+        // status = Valid (00), 14-bit reading = o_max (0x3999)
+        let mut abp = Abp::from_parts(FixedBus(&[0x39, 0x99]), DummyDelay, psi_gauge_150_params());
+
+        let pressure = nb::block!(abp.read()).unwrap();
+        let expected = 150.0 * PressureUnit::Psi.conversion_factor();
+
+        assert!((pressure - expected).abs() < 1.0);
+    }
+
+    #[test]
+    fn read_in_converts_back_to_the_requested_unit()
+    {
+        let mut abp = Abp::from_parts(FixedBus(&[0x39, 0x99]), DummyDelay, psi_gauge_150_params());
+
+        let pressure = nb::block!(abp.read_in(PressureUnit::Psi)).unwrap();
+
+        assert!((pressure - 150.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn pressure_and_temperature_decodes_both_fields_from_one_frame()
+    {
+        // status = Valid (00), pressure reading = o_min (0x0666), temperature reading = 2047 (full scale)
+        let mut abp = Abp::from_parts(FixedBus(&[0x06, 0x66, 0xFF, 0xE0]), DummyDelay, psi_gauge_150_params_with_thermometer());
+
+        let reading = nb::block!(abp.pressure_and_temperature()).unwrap();
+
+        assert!((reading.pressure - 0.0).abs() < 1.0);
+        assert!((reading.temperature - 150.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn pressure_and_temperature_errors_without_a_thermometer()
+    {
+        let mut abp = Abp::from_parts(FixedBus(&[0x06, 0x66, 0xFF, 0xE0]), DummyDelay, psi_gauge_150_params());
+
+        let err = nb::block!(abp.pressure_and_temperature()).unwrap_err();
+
+        assert!(matches!(err, ApbError::ErrorNoThermometer));
+    }
+
+    #[test]
+    fn pressure_and_temperature_would_block_on_a_stale_reading()
+    {
+        // status = Stale (10), rest of the frame is irrelevant
+        let mut abp = Abp::from_parts(FixedBus(&[0x86, 0x66, 0x00, 0x00]), DummyDelay, psi_gauge_150_params_with_thermometer());
+
+        let err = abp.pressure_and_temperature().unwrap_err();
+
+        assert!(matches!(err, nb::Error::WouldBlock));
+    }
+
+    #[test]
+    fn pressure_and_temperature_errors_in_command_mode()
+    {
+        // status = Command (01)
+        let mut abp = Abp::from_parts(FixedBus(&[0x46, 0x66, 0x00, 0x00]), DummyDelay, psi_gauge_150_params_with_thermometer());
+
+        let err = nb::block!(abp.pressure_and_temperature()).unwrap_err();
+
+        assert!(matches!(err, ApbError::ErrorCommandMode));
+    }
+
+    #[test]
+    fn pressure_and_temperature_errors_in_diagnostic_state()
+    {
+        // status = Diagnostic (11)
+        let mut abp = Abp::from_parts(FixedBus(&[0xC6, 0x66, 0x00, 0x00]), DummyDelay, psi_gauge_150_params_with_thermometer());
+
+        let err = nb::block!(abp.pressure_and_temperature()).unwrap_err();
+
+        assert!(matches!(err, ApbError::ErrorDiagnosticState));
+    }
+}
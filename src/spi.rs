@@ -0,0 +1,191 @@
+//! SPI transport for the `Abp` driver.
+
+use embedded_hal as hal;
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+use hal::spi::SpiBus as HalSpiBus;
+use substring::Substring;
+
+use crate::{Abp, AbpBus, ParseError, parse_part_params};
+
+/// Wraps an SPI peripheral together with its chip-select line.
+///
+/// The ABP SPI parts have no address of their own; selecting the device is done purely
+/// through the chip-select pin, so a read simply clocks the frame out while CS is held low.
+#[derive(Debug)]
+pub struct SpiBus<SPI, CS>
+{
+    spi: SPI,
+    cs: CS,
+}
+
+/// Error produced by the SPI transport: either an SPI bus fault or a failure to toggle the
+/// chip-select pin.
+///
+/// The ABP parts give no other signal that CS failed to assert, so a CS fault is surfaced as a
+/// read/write error rather than silently ignored, which would otherwise let a transfer that
+/// never selected the device come back looking like a valid frame.
+#[derive(Copy, Clone, Debug)]
+pub enum SpiBusError<SpiErr, CsErr>
+{
+    Spi(SpiErr),
+    ChipSelect(CsErr),
+}
+
+impl<SPI, CS> AbpBus for SpiBus<SPI, CS>
+where
+    SPI: HalSpiBus,
+    CS: OutputPin,
+{
+    type Error = SpiBusError<SPI::Error, CS::Error>;
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>
+    {
+        self.cs.set_low().map_err(SpiBusError::ChipSelect)?;
+        let result = self.spi.transfer_in_place(buffer).map_err(SpiBusError::Spi);
+        self.cs.set_high().map_err(SpiBusError::ChipSelect)?;
+
+        result
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error>
+    {
+        self.cs.set_low().map_err(SpiBusError::ChipSelect)?;
+        let result = self.spi.write(buffer).map_err(SpiBusError::Spi);
+        self.cs.set_high().map_err(SpiBusError::ChipSelect)?;
+
+        result
+    }
+}
+
+impl<SPI, CS, D> Abp<SpiBus<SPI, CS>, D>
+where
+    SPI: HalSpiBus,
+    CS: OutputPin,
+    D: DelayNs,
+{
+    /// opens a connection to a ABP on a specified SPI, selected through `cs`.
+    ///
+    /// # Panics
+    /// Panics if `part_nr` can't be parsed; use [`Abp::try_new`] for a recoverable path.
+    pub fn new(spi: SPI, cs: CS, delay: D, part_nr: &'static str) -> Self
+    {
+        Self::try_new(spi, cs, delay, part_nr).unwrap()
+    }
+
+    /// opens a connection to a ABP on a specified SPI, without panicking on a malformed
+    /// part number.
+    /// # Errors
+    /// Returns a [`ParseError`] describing which part of `part_nr` couldn't be parsed.
+    pub fn try_new(spi: SPI, cs: CS, delay: D, part_nr: &'static str) -> Result<Self, ParseError>
+    {
+        let params = parse_part_params(part_nr)?;
+
+        match part_nr.substring(12, 13)
+        {
+            "A" | "S" => (),
+             _ => return Err(ParseError::UnsupportedInterface),
+        };
+
+        Ok(Abp::from_parts(SpiBus{spi, cs}, delay, params))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType as PinErrorType;
+    use embedded_hal::spi::ErrorType as SpiErrorType;
+
+    #[derive(Debug)]
+    struct DummySpi;
+
+    impl SpiErrorType for DummySpi
+    {
+        type Error = Infallible;
+    }
+
+    impl HalSpiBus for DummySpi
+    {
+        fn read(&mut self, _words: &mut [u8]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct DummyCs;
+
+    impl PinErrorType for DummyCs
+    {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for DummyCs
+    {
+        fn set_low(&mut self) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error>
+        {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct DummyDelay;
+
+    impl DelayNs for DummyDelay
+    {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn try_new_parses_a_well_formed_part_number()
+    {
+        let abp = Abp::<SpiBus<DummySpi, DummyCs>, DummyDelay>::try_new(DummySpi, DummyCs, DummyDelay, "ABPDNNN150PGAD3");
+
+        assert!(abp.is_ok());
+    }
+
+    #[test]
+    fn try_new_rejects_i2c_interface_digit()
+    {
+        let err = Abp::<SpiBus<DummySpi, DummyCs>, DummyDelay>::try_new(DummySpi, DummyCs, DummyDelay, "ABPDNNN150PG3D3").unwrap_err();
+
+        assert_eq!(err, ParseError::UnsupportedInterface);
+    }
+
+    #[test]
+    fn sleep_and_wake_toggle_command_mode_over_spi()
+    {
+        let mut abp = Abp::<SpiBus<DummySpi, DummyCs>, DummyDelay>::new(DummySpi, DummyCs, DummyDelay, "ABPDNNN150PGSD3");
+
+        assert!(abp.sleep().is_ok());
+        assert!(abp.wake().is_ok());
+    }
+}